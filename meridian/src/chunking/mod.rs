@@ -0,0 +1,408 @@
+//! Semantic chunking of source files for natural-language code search
+//!
+//! Unlike symbol-level embeddings (one vector per function/struct), chunks carry
+//! their exact source provenance (`file_path` + byte range) so a similarity hit
+//! can be turned directly into a jump-to-location in the editor. Chunks are split
+//! along language-aware boundaries where possible (via [`TreeSitterParser`]) and
+//! fall back to paragraph/line windows for unsupported languages or files that
+//! don't parse as code.
+
+use crate::embeddings::{EmbeddingBackend, EmbeddingEngine, FastEmbedBackend};
+use crate::global::GlobalStorage;
+use crate::indexer::TreeSitterParser;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Default maximum chunk size, in tokens (rough estimate of chars/4), kept well
+/// under common embedding model context windows.
+const DEFAULT_MAX_CHUNK_TOKENS: usize = 256;
+
+/// Number of tokens of overlap kept between adjacent windows when a file (or
+/// an oversized symbol) is split into fixed-size windows, so text that
+/// straddles a window boundary is still retrievable from either side. Chunks
+/// taken from separate symbols do not overlap.
+const DEFAULT_OVERLAP_TOKENS: usize = 32;
+
+/// A chunk of source text with its exact provenance and embedding vector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeChunk {
+    pub project_id: String,
+    pub file_path: String,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub vector: Vec<f32>,
+}
+
+/// Splits project files into embeddable chunks and answers natural-language
+/// similarity queries over them
+///
+/// Generic over the [`EmbeddingBackend`] `B` backing `embedding_engine`, so a
+/// chunker can embed against either a local model (the default,
+/// [`FastEmbedBackend`]) or a hosted one.
+pub struct CodeChunker<B: EmbeddingBackend = FastEmbedBackend> {
+    storage: Arc<GlobalStorage>,
+    embedding_engine: Arc<Mutex<EmbeddingEngine<B>>>,
+    parser: Mutex<TreeSitterParser>,
+    max_chunk_tokens: usize,
+    overlap_tokens: usize,
+}
+
+impl<B: EmbeddingBackend + 'static> CodeChunker<B> {
+    /// Create a chunker with default size/overlap budgets
+    pub fn new(storage: Arc<GlobalStorage>, embedding_engine: Arc<Mutex<EmbeddingEngine<B>>>) -> Result<Self> {
+        Ok(Self {
+            storage,
+            embedding_engine,
+            parser: Mutex::new(TreeSitterParser::new()?),
+            max_chunk_tokens: DEFAULT_MAX_CHUNK_TOKENS,
+            overlap_tokens: DEFAULT_OVERLAP_TOKENS,
+        })
+    }
+
+    /// Override the chunk size / overlap budgets (in estimated tokens)
+    pub fn with_budget(mut self, max_chunk_tokens: usize, overlap_tokens: usize) -> Self {
+        self.max_chunk_tokens = max_chunk_tokens;
+        self.overlap_tokens = overlap_tokens;
+        self
+    }
+
+    /// Re-chunk and re-embed a single file, replacing any chunks previously
+    /// stored for it.
+    ///
+    /// Called from `GlobalServer`'s file-watcher change callback (see
+    /// `global/server.rs`) whenever a registered project file is created or
+    /// edited, alongside `SyncManager::handle_file_change`, so the chunk
+    /// index stays incremental rather than requiring a full project re-chunk.
+    ///
+    /// Each chunk's embedding runs on [`tokio::task::spawn_blocking`]: `B` may
+    /// be a remote backend whose `embed` blocks on HTTP + retry backoff for
+    /// seconds at a time, and this is an `async fn` that, per file-watcher
+    /// event, runs on a shared tokio worker.
+    pub async fn handle_file_change(
+        &self,
+        project_id: &str,
+        file_path: &Path,
+        content: &str,
+    ) -> Result<Vec<CodeChunk>> {
+        self.clear_file(project_id, file_path).await?;
+
+        let ranges = self.boundaries_for(file_path, content);
+        let mut chunks = Vec::with_capacity(ranges.len());
+
+        for (start_byte, end_byte) in ranges {
+            let text = content[start_byte..end_byte].to_string();
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let engine = Arc::clone(&self.embedding_engine);
+            let vector = tokio::task::spawn_blocking(move || engine.lock().unwrap().generate_embedding(&text))
+                .await
+                .context("Embedding task panicked")?
+                .with_context(|| format!("Failed to embed chunk of {:?}", file_path))?;
+
+            chunks.push(CodeChunk {
+                project_id: project_id.to_string(),
+                file_path: file_path.to_string_lossy().to_string(),
+                start_byte,
+                end_byte,
+                vector,
+            });
+        }
+
+        for (idx, chunk) in chunks.iter().enumerate() {
+            self.put_chunk(idx, chunk).await?;
+        }
+
+        Ok(chunks)
+    }
+
+    /// Embed a natural-language query and return the top-k most similar chunks
+    /// for a project, each with its exact file/byte range for jump-to. Ranked
+    /// by dot-product similarity when the engine normalizes on store (see
+    /// [`EmbeddingEngine::new_normalized`]), by cosine similarity otherwise.
+    ///
+    /// Scored via [`EmbeddingEngine::most_similar`]'s bounded min-heap rather
+    /// than sorting the whole corpus, so this stays cheap as a project's chunk
+    /// count grows; the chunk's index into `all_chunks` stands in for the
+    /// `SymbolId` that heap is normally keyed by. Runs on
+    /// [`tokio::task::spawn_blocking`] for the same reason as
+    /// [`Self::handle_file_change`]: embedding the query may block on a
+    /// remote backend's HTTP round-trip.
+    pub async fn query(&self, project_id: &str, text: &str, top_k: usize) -> Result<Vec<(CodeChunk, f32)>> {
+        let chunks = self.all_chunks(project_id).await?;
+        let corpus: Vec<(usize, Vec<f32>)> =
+            chunks.iter().enumerate().map(|(idx, chunk)| (idx, chunk.vector.clone())).collect();
+
+        let engine = Arc::clone(&self.embedding_engine);
+        let text = text.to_string();
+        let ranked = tokio::task::spawn_blocking(move || -> Result<Vec<(usize, f32)>> {
+            let mut engine = engine.lock().unwrap();
+            let query_vector = engine.generate_embedding(&text).context("Failed to embed chunk query")?;
+            Ok(engine.most_similar(&query_vector, &corpus, top_k))
+        })
+        .await
+        .context("Query embedding task panicked")??;
+
+        let mut chunks: Vec<Option<CodeChunk>> = chunks.into_iter().map(Some).collect();
+        Ok(ranked
+            .into_iter()
+            .filter_map(|(idx, score)| chunks[idx].take().map(|chunk| (chunk, score)))
+            .collect())
+    }
+
+    /// Compute language-aware chunk boundaries for a file's content.
+    ///
+    /// Uses symbols (functions/classes) from [`TreeSitterParser`] as chunk
+    /// boundaries when the language is supported, splitting any symbol larger
+    /// than the token budget into overlapping sub-windows. Falls back to plain
+    /// overlapping line windows when parsing fails (unsupported language,
+    /// markdown, config files, etc).
+    fn boundaries_for(&self, file_path: &Path, content: &str) -> Vec<(usize, usize)> {
+        let symbol_ranges = self
+            .parser
+            .lock()
+            .unwrap()
+            .parse_file(file_path, content)
+            .ok()
+            .map(|symbols| {
+                let ranges: Vec<(usize, usize)> = symbols
+                    .iter()
+                    .filter_map(|s| byte_range_for_lines(content, s.location.line_start, s.location.line_end))
+                    .collect();
+                filter_contained_ranges(ranges)
+            })
+            .filter(|ranges| !ranges.is_empty());
+
+        let ranges = symbol_ranges.unwrap_or_else(|| self.line_window_boundaries(content));
+
+        ranges
+            .into_iter()
+            .flat_map(|(start, end)| self.split_if_oversized(content, start, end))
+            .collect()
+    }
+
+    /// Overlapping line windows of at most `max_chunk_tokens` (estimated),
+    /// used when a file has no language-aware symbol boundaries.
+    fn line_window_boundaries(&self, content: &str) -> Vec<(usize, usize)> {
+        let max_chars = self.max_chunk_tokens * 4;
+        let overlap_chars = self.overlap_tokens * 4;
+
+        if content.len() <= max_chars {
+            return vec![(0, content.len())];
+        }
+
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start < content.len() {
+            let end = (start + max_chars).min(content.len());
+            let mut end = next_char_boundary(content, end);
+            // End the window just after its last line break, unless that break
+            // falls inside the overlap; a line longer than the window is still
+            // cut at the character budget.
+            if end < content.len() {
+                if let Some(nl) = content[start..end].rfind('\n') {
+                    if nl + 1 > overlap_chars {
+                        end = start + nl + 1;
+                    }
+                }
+            }
+            ranges.push((start, end));
+            if end >= content.len() {
+                break;
+            }
+            start = end.saturating_sub(overlap_chars);
+            start = next_char_boundary(content, start);
+        }
+        ranges
+    }
+
+    /// Split a single boundary into overlapping sub-windows if it exceeds the
+    /// token budget (e.g. a very large function).
+    fn split_if_oversized(&self, content: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+        let max_chars = self.max_chunk_tokens * 4;
+        let overlap_chars = self.overlap_tokens * 4;
+
+        if end.saturating_sub(start) <= max_chars {
+            return vec![(start, end)];
+        }
+
+        let mut ranges = Vec::new();
+        let mut cursor = start;
+        while cursor < end {
+            let chunk_end = (cursor + max_chars).min(end);
+            let chunk_end = next_char_boundary(content, chunk_end);
+            ranges.push((cursor, chunk_end));
+            if chunk_end >= end {
+                break;
+            }
+            cursor = chunk_end.saturating_sub(overlap_chars).max(start);
+            cursor = next_char_boundary(content, cursor);
+        }
+        ranges
+    }
+
+    /// Remove all stored chunks for a file, e.g. because it was deleted.
+    pub async fn remove_file(&self, project_id: &str, file_path: &Path) -> Result<()> {
+        self.clear_file(project_id, file_path).await
+    }
+
+    async fn clear_file(&self, project_id: &str, file_path: &Path) -> Result<()> {
+        let prefix = Self::file_prefix(project_id, file_path);
+        self.storage.delete_prefix(&prefix).await
+    }
+
+    async fn put_chunk(&self, idx: usize, chunk: &CodeChunk) -> Result<()> {
+        let key = format!(
+            "{}{}",
+            Self::file_prefix(&chunk.project_id, Path::new(&chunk.file_path)),
+            idx
+        );
+        let value = serde_json::to_vec(chunk).context("Failed to serialize chunk")?;
+        self.storage.put_raw(&key, &value).await
+    }
+
+    async fn all_chunks(&self, project_id: &str) -> Result<Vec<CodeChunk>> {
+        let prefix = format!("chunk:{}:", project_id);
+        self.storage.scan_raw(&prefix).await
+    }
+
+    fn file_prefix(project_id: &str, file_path: &Path) -> String {
+        format!("chunk:{}:{}:", project_id, file_path.display())
+    }
+}
+
+/// Convert a 1-based, end-inclusive line range (as used by [`crate::types::Location`])
+/// into a byte range within `content`.
+fn byte_range_for_lines(content: &str, line_start: usize, line_end: usize) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    let mut start_byte = None;
+    let mut end_byte = content.len();
+
+    for (idx, line) in content.split_inclusive('\n').enumerate() {
+        let line_no = idx + 1;
+        if line_no == line_start {
+            start_byte = Some(offset);
+        }
+        if line_no == line_end {
+            end_byte = offset + line.len();
+            break;
+        }
+        offset += line.len();
+    }
+
+    start_byte.map(|start| (start, end_byte))
+}
+
+/// Drop any range that fully contains a different range, keeping only the
+/// innermost boundaries.
+///
+/// `TreeSitterParser` returns one symbol per class/struct *and* one per
+/// method nested inside it, so a class's range always wraps its own methods'
+/// ranges. Chunking both would store one large, mostly-redundant chunk per
+/// class plus one near-duplicate chunk per method; keeping only the
+/// innermost ranges avoids that bloat. Lines of a dropped outer range that
+/// fall outside its nested ranges (e.g. a class header or fields) are not
+/// part of any kept range.
+fn filter_contained_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_by_key(|&(start, end)| (start, std::cmp::Reverse(end)));
+    ranges.dedup();
+
+    ranges
+        .iter()
+        .copied()
+        .filter(|&(start, end)| {
+            !ranges.iter().any(|&(other_start, other_end)| {
+                (other_start, other_end) != (start, end) && start <= other_start && other_end <= end
+            })
+        })
+        .collect()
+}
+
+/// Nudge a byte offset forward to the nearest UTF-8 char boundary so chunk
+/// windows never split a multibyte character.
+fn next_char_boundary(content: &str, mut idx: usize) -> usize {
+    while idx < content.len() && !content.is_char_boundary(idx) {
+        idx += 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_filter_contained_ranges_drops_wrapping_class_range() {
+        // A class spanning 0..100 with two methods nested inside it.
+        let ranges = vec![(0, 100), (10, 40), (50, 90)];
+
+        let filtered = filter_contained_ranges(ranges);
+
+        assert_eq!(filtered, vec![(10, 40), (50, 90)]);
+    }
+
+    #[test]
+    fn test_filter_contained_ranges_keeps_disjoint_ranges() {
+        let ranges = vec![(0, 10), (20, 30), (40, 50)];
+
+        let filtered = filter_contained_ranges(ranges.clone());
+
+        assert_eq!(filtered, ranges);
+    }
+
+    async fn setup() -> (CodeChunker, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(GlobalStorage::new(temp_dir.path()).await.unwrap());
+        let engine = Arc::new(Mutex::new(EmbeddingEngine::new().unwrap()));
+        let chunker = CodeChunker::new(storage, engine).unwrap();
+        (chunker, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_chunk_and_query_rust_file() {
+        let (chunker, _temp) = setup().await;
+
+        let content = r#"
+            pub fn add(a: i32, b: i32) -> i32 {
+                a + b
+            }
+
+            pub fn greet(name: &str) -> String {
+                format!("hello, {}", name)
+            }
+        "#;
+
+        let chunks = chunker
+            .handle_file_change("proj-1", Path::new("src/lib.rs"), content)
+            .await
+            .unwrap();
+        assert!(!chunks.is_empty());
+        assert!(chunks.iter().all(|c| c.project_id == "proj-1"));
+
+        let results = chunker.query("proj-1", "adding two numbers", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.file_path, "src/lib.rs");
+    }
+
+    #[tokio::test]
+    async fn test_reindex_replaces_old_chunks() {
+        let (chunker, _temp) = setup().await;
+        let path = Path::new("src/lib.rs");
+
+        chunker
+            .handle_file_change("proj-1", path, "pub fn one() {}")
+            .await
+            .unwrap();
+        chunker
+            .handle_file_change("proj-1", path, "pub fn two() {}\npub fn three() {}")
+            .await
+            .unwrap();
+
+        let all = chunker.all_chunks("proj-1").await.unwrap();
+        assert_eq!(all.len(), 2);
+    }
+}
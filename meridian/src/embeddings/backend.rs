@@ -0,0 +1,75 @@
+//! Pluggable embedding backends for [`super::EmbeddingEngine`]
+//!
+//! `EmbeddingEngine` itself only knows about caching, similarity, and scoring;
+//! the actual "turn these texts into vectors" work is delegated to an
+//! [`EmbeddingBackend`]. [`FastEmbedBackend`] (the default) runs a local
+//! fastembed model; [`super::remote::OpenAiCompatibleBackend`] calls out to a
+//! hosted, OpenAI-compatible embeddings endpoint instead.
+
+use anyhow::{Context, Result};
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use std::sync::Mutex;
+
+/// Turns texts into embedding vectors
+///
+/// Implementations are expected to be cheap to call repeatedly (the engine
+/// calls through on every cache miss) and to return vectors in the same order
+/// as the input texts.
+pub trait EmbeddingBackend: Send + Sync {
+    /// Embed a batch of texts, one vector per input, in order.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// Dimensionality of the vectors this backend produces.
+    fn dimensions(&self) -> usize;
+
+    /// Stable identifier for the model behind this backend, used to tag cache
+    /// entries so embeddings from two different models are never compared.
+    fn model_id(&self) -> &str;
+}
+
+/// Default backend: a local fastembed model
+pub struct FastEmbedBackend {
+    // fastembed's `TextEmbedding::embed` takes `&mut self`; the mutex lets the
+    // backend satisfy `EmbeddingBackend::embed(&self, ...)` like any other
+    // implementation without forcing `EmbeddingEngine` to special-case it.
+    model: Mutex<TextEmbedding>,
+    model_id: String,
+    dimensions: usize,
+}
+
+impl FastEmbedBackend {
+    /// Load the given fastembed model. `dimensions` is the known output width
+    /// for that model (fastembed doesn't expose it without running an
+    /// embedding first, so callers that know their model, e.g.
+    /// `EmbeddingEngine::with_model`, pass it along).
+    pub fn new(model: EmbeddingModel, dimensions: usize) -> Result<Self> {
+        let model_id = format!("{:?}", model);
+        let init_options = InitOptions::new(model);
+        let embedding_model = TextEmbedding::try_new(init_options)
+            .context("Failed to initialize embedding model")?;
+
+        Ok(Self {
+            model: Mutex::new(embedding_model),
+            model_id,
+            dimensions,
+        })
+    }
+}
+
+impl EmbeddingBackend for FastEmbedBackend {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.model
+            .lock()
+            .unwrap()
+            .embed(texts.to_vec(), None)
+            .context("Failed to generate embedding")
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+}
@@ -1,89 +1,320 @@
+pub mod backend;
+pub mod queue;
+pub mod remote;
+
+mod persistent_cache;
+
+pub use backend::{EmbeddingBackend, FastEmbedBackend};
+pub use persistent_cache::PersistentCache;
+pub use queue::EmbeddingQueue;
+pub use remote::{OpenAiCompatibleBackend, RemoteBackendConfig};
+
+use crate::types::SymbolId;
 use anyhow::{Context, Result};
 use dashmap::DashMap;
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use fastembed::EmbeddingModel;
+use ordered_float::NotNan;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
 use std::sync::Arc;
+use tracing::warn;
+
+/// Dimensionality of the default fastembed model (`AllMiniLML6V2`)
+const DEFAULT_MODEL_DIMENSIONS: usize = 384;
+
+/// Default maximum input length, in estimated tokens, for the default
+/// fastembed model (`AllMiniLML6V2`'s 256-token context window).
+const DEFAULT_MAX_TOKENS: usize = 256;
+
+/// (dimensions, max-context-tokens) for the fastembed models
+/// [`EmbeddingEngine::with_model`] is known to support well. Not exhaustive of
+/// every model fastembed ships; anything unlisted falls back to the
+/// `AllMiniLML6V2` defaults above, which is safe for similarly-sized models
+/// but undersizes a larger model's real context window.
+fn model_info(model: &EmbeddingModel) -> (usize, usize) {
+    match model {
+        EmbeddingModel::AllMiniLML6V2 | EmbeddingModel::AllMiniLML6V2Q => (384, 256),
+        EmbeddingModel::AllMiniLML12V2 | EmbeddingModel::AllMiniLML12V2Q => (384, 256),
+        EmbeddingModel::BGESmallENV15 | EmbeddingModel::BGESmallENV15Q => (384, 512),
+        EmbeddingModel::BGEBaseENV15 | EmbeddingModel::BGEBaseENV15Q => (768, 512),
+        EmbeddingModel::BGELargeENV15 | EmbeddingModel::BGELargeENV15Q => (1024, 512),
+        EmbeddingModel::NomicEmbedTextV15 => (768, 8192),
+        EmbeddingModel::MultilingualE5Small => (384, 512),
+        EmbeddingModel::MultilingualE5Base => (768, 512),
+        EmbeddingModel::MultilingualE5Large => (1024, 512),
+        _ => (DEFAULT_MODEL_DIMENSIONS, DEFAULT_MAX_TOKENS),
+    }
+}
+
+/// Rough token estimate shared by truncation (here) and [`queue::EmbeddingQueue`]'s
+/// batching decisions, so the two always agree on what a "token" costs.
+/// Counts chars, not bytes, so multibyte text isn't over-counted (and, for
+/// truncation, isn't mis-cut) relative to the ASCII case this heuristic is
+/// tuned for.
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    (text.chars().count() / 4).max(1)
+}
+
+/// Truncate `text` to at most `max_tokens` estimated tokens, cutting on a char
+/// boundary (not a byte offset) so multibyte text is never split mid-codepoint.
+/// Returns the text unchanged, and `false`, when it already fits.
+fn truncate_to_max_tokens(text: &str, max_tokens: usize) -> (String, bool) {
+    let max_chars = max_tokens.saturating_mul(4).max(1);
+    if text.chars().count() <= max_chars {
+        return (text.to_string(), false);
+    }
+    (text.chars().take(max_chars).collect(), true)
+}
+
+/// Result of embedding text that may have been clipped to fit the model's
+/// context window. Kept separate from the plain `Vec<f32>` returns of
+/// [`EmbeddingEngine::generate_embedding`]/[`EmbeddingEngine::batch_generate`]
+/// so existing callers that don't care about truncation are unaffected.
+#[derive(Debug, Clone)]
+pub struct EmbeddedText {
+    pub embedding: Vec<f32>,
+    /// `true` if the input was longer than `max_tokens` and had to be clipped
+    /// before reaching the backend.
+    pub truncated: bool,
+}
 
 /// Embedding engine for generating vector embeddings of code symbols
-pub struct EmbeddingEngine {
-    model: TextEmbedding,
+///
+/// Generic over the [`EmbeddingBackend`] that actually talks to a model, so
+/// the caching/similarity/scoring machinery here works unchanged whether the
+/// backend is a local fastembed model (the default, [`FastEmbedBackend`]) or
+/// a hosted one (e.g. [`OpenAiCompatibleBackend`]).
+pub struct EmbeddingEngine<B: EmbeddingBackend = FastEmbedBackend> {
+    backend: B,
+    /// Identifies which model produced the cached vectors, so switching models
+    /// can't silently compare embeddings from two different spaces.
+    model_id: String,
     cache: Arc<DashMap<String, Vec<f32>>>,
+    persistent: Option<Arc<PersistentCache>>,
+    /// Maximum input length, in estimated tokens, before text is truncated
+    /// ahead of the backend. Defaulted per model, overridable via
+    /// [`Self::with_max_tokens`].
+    max_tokens: usize,
+    /// When set, every embedding is L2-normalized to unit length before it is
+    /// cached, so similarity against it can use the cheap [`Self::dot_similarity`]
+    /// instead of recomputing both norms on every comparison. Fixed for the
+    /// engine's lifetime (see [`Self::new_normalized`]) so a single engine can
+    /// never mix normalized and raw vectors in its own cache.
+    normalized: bool,
 }
 
-impl EmbeddingEngine {
+impl EmbeddingEngine<FastEmbedBackend> {
     /// Create a new embedding engine with default model
     pub fn new() -> Result<Self> {
         Self::with_model(EmbeddingModel::AllMiniLML6V2)
     }
 
-    /// Create embedding engine with specific model
+    /// Create embedding engine with specific local fastembed model. Its output
+    /// dimensionality and context window are looked up per-model (see
+    /// [`model_info`]), not assumed to match the default model's.
     pub fn with_model(model: EmbeddingModel) -> Result<Self> {
-        let init_options = InitOptions::new(model);
-        let embedding_model = TextEmbedding::try_new(init_options)
-            .context("Failed to initialize embedding model")?;
+        let (dimensions, max_tokens) = model_info(&model);
+        let backend = FastEmbedBackend::new(model, dimensions)?;
+        Ok(Self::with_backend(backend).with_max_tokens(max_tokens))
+    }
+
+    /// Create a new embedding engine, like [`Self::new`], but with every
+    /// embedding normalized to unit length at insertion time. Similarity
+    /// lookups (`most_similar`, `hybrid_score`) then use the cheap
+    /// [`Self::dot_similarity`] instead of recomputing norms on every call.
+    pub fn new_normalized() -> Result<Self> {
+        Ok(Self::new()?.normalized())
+    }
+}
 
-        Ok(Self {
-            model: embedding_model,
+impl<B: EmbeddingBackend> EmbeddingEngine<B> {
+    /// Create an embedding engine driven by an arbitrary [`EmbeddingBackend`],
+    /// e.g. [`OpenAiCompatibleBackend`] for a hosted model.
+    pub fn with_backend(backend: B) -> Self {
+        let model_id = backend.model_id().to_string();
+        Self {
+            backend,
+            model_id,
             cache: Arc::new(DashMap::new()),
-        })
+            persistent: None,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            normalized: false,
+        }
+    }
+
+    /// Override the maximum input length (in estimated tokens) before text is
+    /// truncated ahead of the backend. See [`DEFAULT_MAX_TOKENS`] for the
+    /// out-of-the-box default.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+
+    /// Enable normalize-on-store mode: every embedding computed from here on
+    /// is L2-normalized before being cached. Prefer [`Self::new_normalized`]
+    /// for the default backend; this is the generic-backend equivalent.
+    pub fn normalized(mut self) -> Self {
+        self.normalized = true;
+        self
+    }
+
+    /// Back this engine with a persistent, content-addressed cache on disk at
+    /// `path`, so restarting the process doesn't require re-embedding the
+    /// whole codebase. Entries are keyed by a digest of the symbol text (not
+    /// the raw text itself), so identical bodies across files dedupe, and are
+    /// tagged with the current model's identity so switching models can't
+    /// silently reuse embeddings from a different vector space.
+    pub fn with_persistent_cache(mut self, path: &Path) -> Result<Self> {
+        self.persistent = Some(Arc::new(PersistentCache::open(path, self.model_id.clone())?));
+        Ok(self)
+    }
+
+    /// Persist any buffered writes to the on-disk cache. A no-op when no
+    /// persistent cache is configured.
+    pub fn flush(&self) -> Result<()> {
+        if let Some(persistent) = &self.persistent {
+            persistent.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Digest used to key the persistent cache, independent of raw text length.
+    fn content_digest(text: &str) -> String {
+        blake3::hash(text.as_bytes()).to_hex().to_string()
     }
 
     /// Generate embedding for a single text
     pub fn generate_embedding(&mut self, text: &str) -> Result<Vec<f32>> {
-        // Check cache first
+        Ok(self.generate_embedding_with_info(text)?.embedding)
+    }
+
+    /// Like [`Self::generate_embedding`], but also reports whether `text` had
+    /// to be truncated to `max_tokens` before reaching the backend. The cache
+    /// is always keyed by the original, untruncated text, so repeated lookups
+    /// for the same oversized input still hit.
+    pub fn generate_embedding_with_info(&mut self, text: &str) -> Result<EmbeddedText> {
+        // Check in-memory cache first
         if let Some(cached) = self.cache.get(text) {
-            return Ok(cached.clone());
+            return Ok(EmbeddedText { embedding: cached.clone(), truncated: false });
         }
 
-        // Generate embedding
-        let embeddings = self
-            .model
-            .embed(vec![text.to_string()], None)
-            .context("Failed to generate embedding")?;
+        // Then the on-disk cache, if enabled
+        if let Some(persistent) = &self.persistent {
+            if let Some(mut vector) = persistent.get(&Self::content_digest(text))? {
+                // The disk cache may hold raw vectors from a non-normalizing
+                // engine; normalizing a unit vector again leaves it unchanged.
+                if self.normalized {
+                    l2_normalize(&mut vector);
+                }
+                self.cache.insert(text.to_string(), vector.clone());
+                return Ok(EmbeddedText { embedding: vector, truncated: false });
+            }
+        }
+
+        // True miss: truncate to the model's context window, then ask the backend
+        let (input, truncated) = truncate_to_max_tokens(text, self.max_tokens);
+        let embeddings = self.backend.embed(&[input])?;
 
-        let embedding = embeddings
+        let mut embedding = embeddings
             .into_iter()
             .next()
             .context("No embedding returned")?;
+        if self.normalized {
+            l2_normalize(&mut embedding);
+        }
 
-        // Cache it
+        // Cache it under the original (untruncated) text in both layers. The
+        // in-memory cache is the source of truth for this process; a failed
+        // disk write is logged and skipped rather than losing the embedding
+        // we already paid to compute.
         self.cache.insert(text.to_string(), embedding.clone());
+        if let Some(persistent) = &self.persistent {
+            if let Err(e) = persistent.put(&Self::content_digest(text), &embedding) {
+                warn!("Failed to persist embedding to disk cache: {}", e);
+            }
+        }
 
-        Ok(embedding)
+        Ok(EmbeddedText { embedding, truncated })
     }
 
     /// Generate embeddings for multiple texts in batch
     pub fn batch_generate(&mut self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
-        let mut results = Vec::with_capacity(texts.len());
+        Ok(self
+            .batch_generate_with_info(texts)?
+            .into_iter()
+            .map(|result| result.embedding)
+            .collect())
+    }
+
+    /// Like [`Self::batch_generate`], but reports per-item whether truncation
+    /// to `max_tokens` occurred before reaching the backend.
+    pub fn batch_generate_with_info(&mut self, texts: Vec<&str>) -> Result<Vec<EmbeddedText>> {
+        let mut results: Vec<Option<EmbeddedText>> = Vec::with_capacity(texts.len());
         let mut uncached_texts = Vec::new();
         let mut uncached_indices = Vec::new();
 
-        // Check cache for each text
+        // Check in-memory then on-disk cache for each text
         for (idx, text) in texts.iter().enumerate() {
             if let Some(cached) = self.cache.get(*text) {
-                results.push(Some(cached.clone()));
-            } else {
-                results.push(None);
-                uncached_texts.push(text.to_string());
-                uncached_indices.push(idx);
+                results.push(Some(EmbeddedText { embedding: cached.clone(), truncated: false }));
+                continue;
             }
+
+            if let Some(persistent) = &self.persistent {
+                if let Some(mut vector) = persistent.get(&Self::content_digest(text))? {
+                    if self.normalized {
+                        l2_normalize(&mut vector);
+                    }
+                    self.cache.insert(text.to_string(), vector.clone());
+                    results.push(Some(EmbeddedText { embedding: vector, truncated: false }));
+                    continue;
+                }
+            }
+
+            results.push(None);
+            uncached_texts.push(text.to_string());
+            uncached_indices.push(idx);
         }
 
-        // Generate embeddings for uncached texts
+        // Generate embeddings for true misses, truncating each to the model's
+        // context window first
         if !uncached_texts.is_empty() {
+            let truncations: Vec<(String, bool)> = uncached_texts
+                .iter()
+                .map(|text| truncate_to_max_tokens(text, self.max_tokens))
+                .collect();
+            let inputs: Vec<String> = truncations.iter().map(|(input, _)| input.clone()).collect();
+
             let embeddings = self
-                .model
-                .embed(uncached_texts.clone(), None)
+                .backend
+                .embed(&inputs)
                 .context("Failed to generate batch embeddings")?;
 
-            // Fill in the results and cache them
-            for (embedding, idx) in embeddings.into_iter().zip(uncached_indices.iter()) {
-                results[*idx] = Some(embedding.clone());
-                self.cache.insert(uncached_texts[*idx].clone(), embedding);
+            // Fill in the results and cache them in both layers, keyed by the
+            // original (untruncated) text. The in-memory cache (and the
+            // results returned to the caller) are populated unconditionally;
+            // a disk-write failure on one item is logged and skipped rather
+            // than aborting the rest of the batch and throwing away
+            // embeddings we already paid to compute.
+            for ((mut embedding, idx), (_, truncated)) in
+                embeddings.into_iter().zip(uncached_indices.iter()).zip(truncations.into_iter())
+            {
+                if self.normalized {
+                    l2_normalize(&mut embedding);
+                }
+                if let Some(persistent) = &self.persistent {
+                    if let Err(e) = persistent.put(&Self::content_digest(&uncached_texts[*idx]), &embedding) {
+                        warn!("Failed to persist embedding to disk cache: {}", e);
+                    }
+                }
+                self.cache.insert(uncached_texts[*idx].clone(), embedding.clone());
+                results[*idx] = Some(EmbeddedText { embedding, truncated });
             }
         }
 
         // Unwrap all results (they should all be Some now)
-        Ok(results.into_iter().filter_map(|r| r).collect())
+        Ok(results.into_iter().flatten().collect())
     }
 
     /// Clear the cache
@@ -112,18 +343,330 @@ impl EmbeddingEngine {
 
         dot_product / (norm_a * norm_b)
     }
+
+    /// Similarity between two embeddings that are already unit-length, e.g.
+    /// ones produced by an engine created with [`Self::new_normalized`].
+    /// Skips the norm recomputation `cosine_similarity` does on every call, so
+    /// prefer this when scoring one query against many cached, normalized
+    /// vectors. Mixing normalized and raw vectors here silently returns a
+    /// meaningless score rather than an error — that's what the construction
+    /// mode (`normalized`) exists to prevent: a single engine's cache is
+    /// either all-normalized or all-raw for its whole lifetime.
+    pub fn dot_similarity(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    }
+
+    /// The similarity function matching this engine's construction mode:
+    /// [`Self::dot_similarity`] when normalize-on-store is active, otherwise
+    /// [`Self::cosine_similarity`].
+    fn similarity_fn(&self) -> fn(&[f32], &[f32]) -> f32 {
+        if self.normalized {
+            Self::dot_similarity
+        } else {
+            Self::cosine_similarity
+        }
+    }
+
+    /// Find the `limit` entries in `corpus` most similar to `query`, without
+    /// materializing or fully sorting the whole corpus. Uses
+    /// [`Self::dot_similarity`] when this engine normalizes on store, and
+    /// [`Self::cosine_similarity`] otherwise.
+    ///
+    /// Uses a bounded min-heap: each candidate is pushed, and the smallest
+    /// entry is popped whenever the heap exceeds `limit`, so memory stays
+    /// `O(limit)` regardless of corpus size. NaN similarities (e.g. from NaN
+    /// vector components) are skipped rather than allowed to corrupt the
+    /// ordering; zero vectors and mismatched lengths score `0.0` and are
+    /// ranked like any other candidate. Ties are broken deterministically by
+    /// the identifier's own `Ord`, so results are stable across runs. Generic
+    /// over the identifier type `Id` so it works equally for `SymbolId`
+    /// corpora and e.g. chunk-index corpora.
+    pub fn most_similar<Id: Clone + Ord>(
+        &self,
+        query: &[f32],
+        corpus: &[(Id, Vec<f32>)],
+        limit: usize,
+    ) -> Vec<(Id, f32)> {
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        let similarity_fn = self.similarity_fn();
+        let mut heap: BinaryHeap<Reverse<(NotNan<f32>, Id)>> = BinaryHeap::with_capacity(limit + 1);
+
+        for (id, vector) in corpus {
+            let similarity = similarity_fn(query, vector);
+            let Ok(similarity) = NotNan::new(similarity) else {
+                continue;
+            };
+
+            heap.push(Reverse((similarity, id.clone())));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+
+        let mut results: Vec<(NotNan<f32>, Id)> = heap.into_iter().map(|Reverse(entry)| entry).collect();
+        results.sort_by(|a, b| b.cmp(a));
+
+        results
+            .into_iter()
+            .map(|(similarity, id)| (id, similarity.into_inner()))
+            .collect()
+    }
+
+    /// Fuse lexical and semantic similarity for a set of candidates
+    ///
+    /// `semantic_ratio` controls the blend: `1.0` weighs purely on semantic
+    /// similarity (same ranking as [`cosine_similarity`] against `query_embedding`),
+    /// `0.0` weighs purely on keyword overlap. Both the keyword and semantic score
+    /// lists are min-max normalized to `[0, 1]` independently before combining, so
+    /// callers can compare `final_score` across queries of very different scale.
+    pub fn hybrid_score(
+        &self,
+        query_text: &str,
+        query_embedding: &[f32],
+        candidates: &[HybridCandidate<'_>],
+        semantic_ratio: f32,
+    ) -> Vec<HybridMatch> {
+        let semantic_ratio = semantic_ratio.clamp(0.0, 1.0);
+        let query_tokens = tokenize_identifiers(query_text);
+        let similarity_fn = self.similarity_fn();
+
+        let keyword_scores: Vec<f32> = candidates
+            .iter()
+            .map(|c| keyword_score(&query_tokens, c.text))
+            .collect();
+        let semantic_scores: Vec<f32> = candidates
+            .iter()
+            .map(|c| similarity_fn(query_embedding, c.embedding))
+            .collect();
+
+        let keyword_norm = min_max_normalize(&keyword_scores);
+        let semantic_norm = min_max_normalize(&semantic_scores);
+
+        let mut matches: Vec<HybridMatch> = candidates
+            .iter()
+            .zip(keyword_norm.into_iter())
+            .zip(semantic_norm.into_iter())
+            .map(|((candidate, keyword_score), semantic_score)| HybridMatch {
+                id: candidate.id.clone(),
+                final_score: (1.0 - semantic_ratio) * keyword_score + semantic_ratio * semantic_score,
+                keyword_score,
+                semantic_score,
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.final_score.partial_cmp(&a.final_score).unwrap_or(std::cmp::Ordering::Equal));
+        matches
+    }
+}
+
+/// A scored candidate for [`EmbeddingEngine::hybrid_score`]: its display/search
+/// text for lexical matching plus a precomputed embedding for semantic matching.
+pub struct HybridCandidate<'a> {
+    pub id: SymbolId,
+    pub text: &'a str,
+    pub embedding: &'a [f32],
+}
+
+/// One candidate's fused score from [`EmbeddingEngine::hybrid_score`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridMatch {
+    pub id: SymbolId,
+    pub final_score: f32,
+    pub keyword_score: f32,
+    pub semantic_score: f32,
+}
+
+/// Normalized term-frequency keyword score: the fraction of `candidate`'s
+/// identifier tokens that match a query token, weighted by how often each
+/// query token recurs in the candidate.
+fn keyword_score(query_tokens: &[String], candidate_text: &str) -> f32 {
+    if query_tokens.is_empty() {
+        return 0.0;
+    }
+
+    let candidate_tokens = tokenize_identifiers(candidate_text);
+    if candidate_tokens.is_empty() {
+        return 0.0;
+    }
+
+    let matches: usize = query_tokens
+        .iter()
+        .map(|qt| candidate_tokens.iter().filter(|ct| *ct == qt).count())
+        .sum();
+
+    matches as f32 / candidate_tokens.len() as f32
+}
+
+/// Split text into lowercase identifier tokens, breaking on non-alphanumeric
+/// separators as well as `camelCase`/`PascalCase` boundaries so `getUserName`
+/// tokenizes the same as `get_user_name`.
+fn tokenize_identifiers(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for ch in text.chars() {
+        if ch.is_alphanumeric() {
+            if ch.is_uppercase() && prev_lower {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            prev_lower = ch.is_lowercase();
+            current.extend(ch.to_lowercase());
+        } else {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Min-max normalize a list of scores to `[0, 1]`. A degenerate list (empty, or
+/// every value equal) normalizes to all-`1.0` rather than dividing by zero.
+fn min_max_normalize(scores: &[f32]) -> Vec<f32> {
+    if scores.is_empty() {
+        return Vec::new();
+    }
+
+    let min = scores.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+
+    if (max - min).abs() < f32::EPSILON {
+        return vec![1.0; scores.len()];
+    }
+
+    scores.iter().map(|s| (s - min) / (max - min)).collect()
+}
+
+/// Normalize `vector` to unit L2 length in place, so its similarity against
+/// another unit vector collapses to a plain dot product (see
+/// [`EmbeddingEngine::dot_similarity`]). An all-zero vector has no direction
+/// to normalize to, so it's left untouched rather than dividing by zero.
+fn l2_normalize(vector: &mut [f32]) {
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return;
+    }
+
+    for x in vector.iter_mut() {
+        *x /= norm;
+    }
 }
 
-impl Default for EmbeddingEngine {
+impl Default for EmbeddingEngine<FastEmbedBackend> {
     fn default() -> Self {
         Self::new().expect("Failed to create default embedding engine")
     }
 }
 
+/// A deterministic, in-memory [`EmbeddingBackend`] used by tests that need a
+/// pluggable backend without loading a real model.
+#[cfg(test)]
+struct FakeBackend {
+    dimensions: usize,
+}
+
+#[cfg(test)]
+impl EmbeddingBackend for FakeBackend {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|t| vec![t.len() as f32; self.dimensions])
+            .collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn model_id(&self) -> &str {
+        "fake-backend"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_model_info_differs_per_model() {
+        assert_eq!(model_info(&EmbeddingModel::AllMiniLML6V2), (384, 256));
+        assert_eq!(model_info(&EmbeddingModel::BGEBaseENV15), (768, 512));
+    }
+
+    #[test]
+    fn test_custom_backend_is_used_through_with_backend() {
+        let mut engine = EmbeddingEngine::with_backend(FakeBackend { dimensions: 4 });
+
+        let embedding = engine.generate_embedding("abc").unwrap();
+        assert_eq!(embedding, vec![3.0; 4]);
+        assert_eq!(engine.cache_size(), 1);
+    }
+
+    #[test]
+    fn test_oversized_input_is_truncated_before_reaching_backend() {
+        let mut engine = EmbeddingEngine::with_backend(FakeBackend { dimensions: 1 }).with_max_tokens(2);
+        // max_tokens=2 -> 8 chars; FakeBackend echoes the (possibly truncated)
+        // input length, so a longer embedding would mean truncation didn't happen.
+        let text = "x".repeat(100);
+
+        let result = engine.generate_embedding_with_info(&text).unwrap();
+
+        assert!(result.truncated);
+        assert_eq!(result.embedding, vec![8.0]);
+    }
+
+    #[test]
+    fn test_truncated_entry_is_cached_under_original_text() {
+        let mut engine = EmbeddingEngine::with_backend(FakeBackend { dimensions: 1 }).with_max_tokens(2);
+        let text = "x".repeat(100);
+
+        let first = engine.generate_embedding_with_info(&text).unwrap();
+        let second = engine.generate_embedding_with_info(&text).unwrap();
+
+        // The repeated lookup hits the cache (keyed by the original, untruncated
+        // text), so it returns the same vector without being reported as truncated.
+        assert_eq!(first.embedding, second.embedding);
+        assert!(!second.truncated);
+        assert_eq!(engine.cache_size(), 1);
+    }
+
+    #[test]
+    fn test_input_within_budget_is_not_truncated() {
+        let mut engine = EmbeddingEngine::with_backend(FakeBackend { dimensions: 1 }).with_max_tokens(100);
+
+        let result = engine.generate_embedding_with_info("short").unwrap();
+
+        assert!(!result.truncated);
+        assert_eq!(result.embedding, vec![5.0]);
+    }
+
+    #[test]
+    fn test_batch_generate_with_info_reports_per_item_truncation() {
+        let mut engine = EmbeddingEngine::with_backend(FakeBackend { dimensions: 1 }).with_max_tokens(2);
+
+        let results = engine
+            .batch_generate_with_info(vec!["short", &"z".repeat(100)])
+            .unwrap();
+
+        assert!(!results[0].truncated);
+        assert!(results[1].truncated);
+    }
+
     #[test]
     fn test_generate_embedding() {
         let mut engine = EmbeddingEngine::new().expect("Failed to create engine");
@@ -238,4 +781,179 @@ mod tests {
         let similarity = EmbeddingEngine::cosine_similarity(&vec1, &vec2);
         assert_eq!(similarity, 0.0);
     }
+
+    fn fake_engine() -> EmbeddingEngine<FakeBackend> {
+        EmbeddingEngine::with_backend(FakeBackend { dimensions: 1 })
+    }
+
+    #[test]
+    fn test_hybrid_score_pure_keyword() {
+        let engine = fake_engine();
+        let query_embedding = vec![0.0; 4];
+        let candidates = vec![
+            HybridCandidate {
+                id: SymbolId::new("a"),
+                text: "fn get_user_name() -> String",
+                embedding: &[0.0; 4],
+            },
+            HybridCandidate {
+                id: SymbolId::new("b"),
+                text: "fn totally_unrelated() -> i32",
+                embedding: &[0.0; 4],
+            },
+        ];
+
+        let matches = engine.hybrid_score("getUserName", &query_embedding, &candidates, 0.0);
+
+        assert_eq!(matches[0].id, SymbolId::new("a"));
+        assert!(matches[0].keyword_score > matches[1].keyword_score);
+        assert_eq!(matches[0].final_score, matches[0].keyword_score);
+    }
+
+    #[test]
+    fn test_hybrid_score_pure_semantic_matches_cosine_ranking() {
+        let engine = fake_engine();
+        let query_embedding = vec![1.0, 0.0];
+        let candidates = vec![
+            HybridCandidate {
+                id: SymbolId::new("near"),
+                text: "irrelevant text",
+                embedding: &[0.9, 0.1],
+            },
+            HybridCandidate {
+                id: SymbolId::new("far"),
+                text: "irrelevant text",
+                embedding: &[0.0, 1.0],
+            },
+        ];
+
+        let matches = engine.hybrid_score("query", &query_embedding, &candidates, 1.0);
+
+        assert_eq!(matches[0].id, SymbolId::new("near"));
+    }
+
+    #[test]
+    fn test_hybrid_score_uses_dot_path_when_normalized() {
+        let engine = fake_engine().normalized();
+        let mut query_embedding = vec![2.0, 0.0];
+        l2_normalize(&mut query_embedding);
+
+        let mut near = vec![0.9, 0.1];
+        l2_normalize(&mut near);
+        let mut far = vec![0.0, 1.0];
+        l2_normalize(&mut far);
+
+        let candidates = vec![
+            HybridCandidate { id: SymbolId::new("near"), text: "irrelevant", embedding: &near },
+            HybridCandidate { id: SymbolId::new("far"), text: "irrelevant", embedding: &far },
+        ];
+
+        let matches = engine.hybrid_score("query", &query_embedding, &candidates, 1.0);
+
+        assert_eq!(matches[0].id, SymbolId::new("near"));
+        // With unit vectors, the dot path and cosine path agree numerically.
+        assert!((matches[0].semantic_score - 1.0).abs() < 0.01);
+
+        // A non-unit candidate tells the two paths apart: its raw dot product
+        // with the query (2.0) outranks "near", while its cosine (~0.71) would not.
+        let big = [2.0, 2.0];
+        let with_big = vec![
+            HybridCandidate { id: SymbolId::new("near"), text: "irrelevant", embedding: &near },
+            HybridCandidate { id: SymbolId::new("big"), text: "irrelevant", embedding: &big },
+        ];
+        let matches = engine.hybrid_score("query", &query_embedding, &with_big, 1.0);
+        assert_eq!(matches[0].id, SymbolId::new("big"));
+    }
+
+    #[test]
+    fn test_most_similar_bounded_heap() {
+        let engine = fake_engine();
+        let query = vec![1.0, 0.0];
+        let corpus = vec![
+            (SymbolId::new("a"), vec![1.0, 0.0]),
+            (SymbolId::new("b"), vec![0.7, 0.3]),
+            (SymbolId::new("c"), vec![0.0, 1.0]),
+            (SymbolId::new("d"), vec![0.9, 0.1]),
+        ];
+
+        let top2 = engine.most_similar(&query, &corpus, 2);
+
+        assert_eq!(top2.len(), 2);
+        assert_eq!(top2[0].0, SymbolId::new("a"));
+        assert_eq!(top2[1].0, SymbolId::new("d"));
+        assert!(top2[0].1 >= top2[1].1);
+    }
+
+    #[test]
+    fn test_most_similar_deterministic_ties() {
+        let engine = fake_engine();
+        let query = vec![1.0, 0.0];
+        let corpus = vec![
+            (SymbolId::new("b"), vec![1.0, 0.0]),
+            (SymbolId::new("a"), vec![1.0, 0.0]),
+        ];
+
+        let top = engine.most_similar(&query, &corpus, 1);
+
+        // Equal similarity: tie is broken deterministically by SymbolId, not insertion order.
+        assert_eq!(top[0].0, SymbolId::new("b"));
+    }
+
+    #[test]
+    fn test_most_similar_uses_dot_path_when_normalized() {
+        let engine = fake_engine().normalized();
+        let mut query = vec![3.0, 4.0];
+        l2_normalize(&mut query);
+        let mut close = vec![3.0, 4.0];
+        l2_normalize(&mut close);
+
+        let corpus = vec![(SymbolId::new("close"), close), (SymbolId::new("far"), vec![1.0, 0.0])];
+
+        let top = engine.most_similar(&query, &corpus, 1);
+
+        assert_eq!(top[0].0, SymbolId::new("close"));
+        assert!((top[0].1 - 1.0).abs() < 0.01);
+
+        // A half-length vector in the query's direction scores its raw dot
+        // product (0.5) on the dot path; cosine would score it 1.0.
+        let half = vec![(SymbolId::new("half"), vec![0.3, 0.4])];
+        let top = engine.most_similar(&query, &half, 1);
+        assert!((top[0].1 - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_l2_normalize_produces_unit_length() {
+        let mut v = vec![3.0, 4.0];
+        l2_normalize(&mut v);
+        let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_l2_normalize_leaves_zero_vector_untouched() {
+        let mut v = vec![0.0, 0.0, 0.0];
+        l2_normalize(&mut v);
+        assert_eq!(v, vec![0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_dot_similarity_matches_cosine_for_unit_vectors() {
+        let mut a = vec![1.0, 2.0, 3.0];
+        let mut b = vec![2.0, 0.0, 1.0];
+        let cosine = EmbeddingEngine::<FakeBackend>::cosine_similarity(&a, &b);
+        l2_normalize(&mut a);
+        l2_normalize(&mut b);
+
+        let dot = EmbeddingEngine::<FakeBackend>::dot_similarity(&a, &b);
+
+        assert!((dot - cosine).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_normalized_engine_caches_unit_length_vectors() {
+        let mut engine = fake_engine().normalized();
+        let embedding = engine.generate_embedding("abc").unwrap(); // FakeBackend: [3.0; 1]
+        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+    }
 }
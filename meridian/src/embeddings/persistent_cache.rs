@@ -0,0 +1,109 @@
+//! On-disk, content-addressed backing store for [`super::EmbeddingEngine`]
+//!
+//! Entries are keyed by a blake3 digest of the embedded text rather than the
+//! text itself, so identical symbol bodies across different files dedupe to a
+//! single on-disk record. Every record is tagged with the model identity that
+//! produced it, so embeddings computed by a different model are never handed
+//! back as if they were comparable to the current one.
+
+use anyhow::{Context, Result};
+use rocksdb::{Options, DB};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+struct CachedEmbedding {
+    model_id: String,
+    vector: Vec<f32>,
+}
+
+/// Persistent key-value store backing [`super::EmbeddingEngine`]'s in-memory cache
+pub struct PersistentCache {
+    db: DB,
+    model_id: String,
+}
+
+impl PersistentCache {
+    /// Open (or create) the on-disk cache at `path`, tagging new entries with
+    /// `model_id` so embeddings from a previous model are never matched.
+    pub fn open(path: &Path, model_id: String) -> Result<Self> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+
+        let db = DB::open(&opts, path)
+            .with_context(|| format!("Failed to open persistent embedding cache at {:?}", path))?;
+
+        Ok(Self { db, model_id })
+    }
+
+    /// Look up a cached embedding by content digest. Returns `None` both on a
+    /// true miss and when the stored entry was produced by a different model.
+    pub fn get(&self, digest: &str) -> Result<Option<Vec<f32>>> {
+        let Some(bytes) = self.db.get(digest)? else {
+            return Ok(None);
+        };
+
+        let cached: CachedEmbedding =
+            serde_json::from_slice(&bytes).context("Failed to deserialize cached embedding")?;
+
+        if cached.model_id != self.model_id {
+            return Ok(None);
+        }
+
+        Ok(Some(cached.vector))
+    }
+
+    /// Store an embedding under its content digest, tagged with the current model.
+    pub fn put(&self, digest: &str, vector: &[f32]) -> Result<()> {
+        let cached = CachedEmbedding {
+            model_id: self.model_id.clone(),
+            vector: vector.to_vec(),
+        };
+        let bytes = serde_json::to_vec(&cached).context("Failed to serialize embedding for cache")?;
+
+        self.db
+            .put(digest, bytes)
+            .with_context(|| format!("Failed to write cached embedding for digest {}", digest))
+    }
+
+    /// Flush buffered writes to disk.
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush().context("Failed to flush persistent embedding cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_put_and_get_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = PersistentCache::open(temp_dir.path(), "model-a".to_string()).unwrap();
+
+        cache.put("digest-1", &[0.1, 0.2, 0.3]).unwrap();
+
+        let fetched = cache.get("digest-1").unwrap();
+        assert_eq!(fetched, Some(vec![0.1, 0.2, 0.3]));
+    }
+
+    #[test]
+    fn test_entries_from_a_different_model_are_invisible() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let cache = PersistentCache::open(temp_dir.path(), "model-a".to_string()).unwrap();
+            cache.put("digest-1", &[0.1, 0.2, 0.3]).unwrap();
+        }
+
+        let cache = PersistentCache::open(temp_dir.path(), "model-b".to_string()).unwrap();
+        assert_eq!(cache.get("digest-1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_missing_digest_returns_none() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = PersistentCache::open(temp_dir.path(), "model-a".to_string()).unwrap();
+        assert_eq!(cache.get("nope").unwrap(), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Token-aware batching for [`EmbeddingEngine`]
+//!
+//! `EmbeddingEngine::batch_generate` embeds whatever the caller hands it in a
+//! single `model.embed` call, which is fine for a handful of symbols but
+//! inefficient for thousands of small ones and risky for a few very large
+//! ones. `EmbeddingQueue` instead accepts items incrementally and flushes a
+//! batch as soon as the next item would push it over a token budget, so each
+//! call to the underlying model sees a roughly uniform amount of work.
+
+use super::{estimate_tokens, EmbeddingBackend, EmbeddingEngine, FastEmbedBackend};
+use anyhow::Result;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+/// Accumulates `(id, text)` pairs and flushes them to an [`EmbeddingEngine`] in
+/// batches sized by estimated token count rather than item count.
+///
+/// Generic over the caller's identifier type `Id` so it can be used with
+/// `SymbolId`, a `PathBuf`, or any other handle the caller wants to get back
+/// alongside each embedding, and over the engine's [`EmbeddingBackend`] `B` so
+/// a queue can batch against either a local or a remote model.
+pub struct EmbeddingQueue<Id, B: EmbeddingBackend = FastEmbedBackend> {
+    engine: Arc<Mutex<EmbeddingEngine<B>>>,
+    max_batch_tokens: usize,
+    pending: Vec<(Id, String)>,
+    pending_tokens: usize,
+}
+
+impl<Id, B: EmbeddingBackend> EmbeddingQueue<Id, B> {
+    /// Create a queue that flushes to `engine` once the accumulated estimated
+    /// token count would exceed `max_batch_tokens`.
+    pub fn new(engine: Arc<Mutex<EmbeddingEngine<B>>>, max_batch_tokens: usize) -> Self {
+        Self {
+            engine,
+            max_batch_tokens: max_batch_tokens.max(1),
+            pending: Vec::new(),
+            pending_tokens: 0,
+        }
+    }
+
+    /// Add an item to the queue. If adding it would exceed the token budget,
+    /// the currently accumulated batch is flushed first and its embeddings are
+    /// returned (an empty vec means nothing was flushed yet). An item whose
+    /// own estimated size exceeds the budget is flushed by itself immediately
+    /// rather than being held back waiting for a batch that can never form.
+    pub fn push(&mut self, id: Id, text: String) -> Result<Vec<(Id, Vec<f32>)>> {
+        let estimated = estimate_tokens(&text);
+        let mut flushed = Vec::new();
+
+        if !self.pending.is_empty() && self.pending_tokens + estimated > self.max_batch_tokens {
+            flushed = self.flush_batch()?;
+        }
+
+        self.pending_tokens += estimated;
+        self.pending.push((id, text));
+
+        if estimated > self.max_batch_tokens {
+            flushed.extend(self.flush_batch()?);
+        }
+
+        Ok(flushed)
+    }
+
+    /// Flush any remaining items and return their embeddings.
+    pub fn drain(&mut self) -> Result<Vec<(Id, Vec<f32>)>> {
+        self.flush_batch()
+    }
+
+    /// Number of items currently buffered, awaiting a flush.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn flush_batch(&mut self) -> Result<Vec<(Id, Vec<f32>)>> {
+        if self.pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let batch = std::mem::take(&mut self.pending);
+        self.pending_tokens = 0;
+
+        // `batch_generate` embeds the whole batch in one backend call before
+        // caching anything, so a failed call leaves none of the batch in the
+        // in-memory cache. On-disk cache writes (when enabled) are per item;
+        // one that fails is logged and skipped.
+        let texts: Vec<&str> = batch.iter().map(|(_, text)| text.as_str()).collect();
+        let embeddings = self.engine.lock().unwrap().batch_generate(texts)?;
+
+        Ok(batch
+            .into_iter()
+            .zip(embeddings)
+            .map(|((id, _text), embedding)| (id, embedding))
+            .collect())
+    }
+}
+
+impl<Id: Clone + Eq + Hash, B: EmbeddingBackend> EmbeddingQueue<Id, B> {
+    /// Total estimated tokens currently buffered, awaiting a flush.
+    pub fn pending_tokens(&self) -> usize {
+        self.pending_tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_queue(max_batch_tokens: usize) -> EmbeddingQueue<&'static str> {
+        let engine = Arc::new(Mutex::new(EmbeddingEngine::new().expect("Failed to create engine")));
+        EmbeddingQueue::new(engine, max_batch_tokens)
+    }
+
+    #[test]
+    fn test_flushes_when_budget_exceeded() {
+        // Each 40-char item estimates to 10 tokens; a budget of 15 means the
+        // first item alone fits (10 <= 15) but the second pushes the running
+        // total to 20, which strictly exceeds the budget and must flush.
+        let mut queue = new_queue(15);
+
+        let flushed_first = queue.push("a", "x".repeat(40)).unwrap();
+        assert!(flushed_first.is_empty());
+
+        // Adding another large item should flush the first before buffering the second.
+        let flushed_second = queue.push("b", "y".repeat(40)).unwrap();
+        assert_eq!(flushed_second.len(), 1);
+        assert_eq!(flushed_second[0].0, "a");
+
+        let remaining = queue.drain().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, "b");
+    }
+
+    #[test]
+    fn test_oversized_item_goes_out_alone() {
+        let mut queue = new_queue(10);
+
+        let flushed = queue.push("huge", "z".repeat(1000)).unwrap();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].0, "huge");
+        assert_eq!(queue.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_drain_on_empty_queue_is_noop() {
+        let mut queue = new_queue(100);
+        assert!(queue.drain().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+//! Hosted, OpenAI-compatible [`EmbeddingBackend`]
+//!
+//! Lets `EmbeddingEngine` be pointed at a hosted embedding model instead of a
+//! local fastembed one, for users who want larger models than they can run
+//! on-device. Requests are chunked to respect the provider's per-request item
+//! and token limits, and HTTP 429 responses are retried with exponential
+//! backoff (honoring `Retry-After` when the provider sends one) rather than
+//! surfaced directly.
+
+use super::{estimate_tokens, EmbeddingBackend};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Base retry delay before the first backoff doubling
+const BASE_BACKOFF_MS: u64 = 500;
+
+/// Upper bound on the backoff delay, regardless of attempt count
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+/// Default cap on estimated tokens sent in a single request, independent of
+/// the item-count cap below. Conservative relative to common provider limits
+/// (e.g. OpenAI's embeddings endpoint caps at 300k tokens/request).
+const DEFAULT_MAX_TOKENS_PER_REQUEST: usize = 250_000;
+
+/// Configuration for [`OpenAiCompatibleBackend`]
+#[derive(Debug, Clone)]
+pub struct RemoteBackendConfig {
+    /// Base URL of the provider's API, e.g. `https://api.openai.com/v1`
+    pub base_url: String,
+
+    /// Bearer token sent as `Authorization: Bearer <api_key>`, if required
+    pub api_key: Option<String>,
+
+    /// Model name sent in the request body
+    pub model: String,
+
+    /// Output vector dimensionality for `model`
+    pub dimensions: usize,
+
+    /// Maximum number of texts sent in a single request, to respect the
+    /// provider's per-request item limit
+    pub max_items_per_request: usize,
+
+    /// Maximum total estimated tokens (see [`estimate_tokens`]) sent in a
+    /// single request, to respect the provider's per-request token limit
+    /// independently of the item-count limit above.
+    pub max_tokens_per_request: usize,
+
+    /// Maximum number of retries on HTTP 429 before giving up
+    pub max_retries: u32,
+}
+
+impl RemoteBackendConfig {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>, dimensions: usize) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: None,
+            model: model.into(),
+            dimensions,
+            max_items_per_request: 96,
+            max_tokens_per_request: DEFAULT_MAX_TOKENS_PER_REQUEST,
+            max_retries: 5,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+}
+
+/// [`EmbeddingBackend`] that calls an OpenAI-compatible `/embeddings` endpoint
+pub struct OpenAiCompatibleBackend {
+    client: reqwest::blocking::Client,
+    config: RemoteBackendConfig,
+}
+
+impl OpenAiCompatibleBackend {
+    pub fn new(config: RemoteBackendConfig) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::blocking::Client::new(),
+            config,
+        })
+    }
+
+    /// Embed a single request-sized chunk, retrying on rate limiting.
+    fn embed_chunk(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.config.base_url.trim_end_matches('/'));
+        let mut attempt = 0u32;
+
+        loop {
+            let mut request = self.client.post(&url).json(&json!({
+                "model": self.config.model,
+                "input": texts,
+            }));
+            if let Some(api_key) = &self.config.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            let response = request
+                .send()
+                .context("Failed to reach remote embedding endpoint")?;
+
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                attempt += 1;
+                if attempt > self.config.max_retries {
+                    bail!(
+                        "Remote embedding endpoint rate-limited after {} retries",
+                        self.config.max_retries
+                    );
+                }
+
+                let delay = retry_after_delay(&response)
+                    .unwrap_or_else(|| backoff_with_jitter(attempt - 1, BASE_BACKOFF_MS, MAX_BACKOFF_MS));
+                std::thread::sleep(delay);
+                continue;
+            }
+
+            let response = response
+                .error_for_status()
+                .context("Remote embedding endpoint returned an error")?;
+
+            let mut body: EmbeddingsResponse = response
+                .json()
+                .context("Failed to parse remote embedding response")?;
+
+            body.data.sort_by_key(|datum| datum.index);
+            return Ok(body.data.into_iter().map(|datum| datum.embedding).collect());
+        }
+    }
+}
+
+impl EmbeddingBackend for OpenAiCompatibleBackend {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for chunk in chunk_by_budget(
+            texts,
+            self.config.max_items_per_request.max(1),
+            self.config.max_tokens_per_request.max(1),
+        ) {
+            embeddings.extend(self.embed_chunk(chunk)?);
+        }
+        Ok(embeddings)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.config.dimensions
+    }
+
+    fn model_id(&self) -> &str {
+        &self.config.model
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingDatum>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingDatum {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+/// Split `texts` into request-sized batches that respect both `max_items` and
+/// `max_tokens` (estimated per-item via [`estimate_tokens`]). A single item
+/// whose own estimate already exceeds `max_tokens` is still sent alone rather
+/// than dropped — it's the provider's call whether to reject it, not ours.
+fn chunk_by_budget<'a>(texts: &'a [String], max_items: usize, max_tokens: usize) -> Vec<&'a [String]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut count = 0;
+    let mut tokens = 0;
+
+    for (idx, text) in texts.iter().enumerate() {
+        let estimated = estimate_tokens(text);
+        if count > 0 && (count + 1 > max_items || tokens + estimated > max_tokens) {
+            chunks.push(&texts[start..idx]);
+            start = idx;
+            count = 0;
+            tokens = 0;
+        }
+        count += 1;
+        tokens += estimated;
+    }
+
+    if start < texts.len() {
+        chunks.push(&texts[start..]);
+    }
+
+    chunks
+}
+
+/// Delay implied by a `Retry-After` header, if the response sent one.
+fn retry_after_delay(response: &reqwest::blocking::Response) -> Option<Duration> {
+    let header = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    let seconds: u64 = header.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Exponential backoff (base `base_ms`, doubling, capped at `cap_ms`) with
+/// full jitter: the returned delay is uniformly random in `[0, cap)` rather
+/// than a fixed value, so retrying clients don't all wake up in lockstep.
+fn backoff_with_jitter(attempt: u32, base_ms: u64, cap_ms: u64) -> Duration {
+    let cap = base_ms.saturating_mul(1u64 << attempt.min(16)).min(cap_ms);
+    Duration::from_millis(random_below(cap.max(1)))
+}
+
+/// A cheap, non-cryptographic random value in `[0, bound)`, good enough for
+/// jitter without pulling in a dedicated RNG dependency.
+fn random_below(bound: u64) -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new().build_hasher().finish() % bound
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_by_budget_splits_on_item_count() {
+        let texts: Vec<String> = (0..5).map(|i| format!("t{}", i)).collect();
+        let chunks = chunk_by_budget(&texts, 2, usize::MAX);
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_chunk_by_budget_splits_on_token_budget() {
+        // Each 40-char text estimates to 10 tokens; a budget of 15 fits one
+        // item per chunk but never two.
+        let texts: Vec<String> = (0..3).map(|_| "x".repeat(40)).collect();
+        let chunks = chunk_by_budget(&texts, usize::MAX, 15);
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn test_chunk_by_budget_sends_oversized_item_alone() {
+        let texts = vec!["x".repeat(1000)];
+        let chunks = chunk_by_budget(&texts, usize::MAX, 10);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 1);
+    }
+
+    #[test]
+    fn test_backoff_respects_cap() {
+        for attempt in 0..20 {
+            let delay = backoff_with_jitter(attempt, BASE_BACKOFF_MS, MAX_BACKOFF_MS);
+            assert!(delay.as_millis() as u64 <= MAX_BACKOFF_MS);
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_with_attempt_count() {
+        // The *ceiling* of the jittered delay should grow with attempt count,
+        // even though any individual sample is randomized down toward zero.
+        // Sample several delays per attempt and compare the observed maxima
+        // against the real function, rather than against a copy of its formula.
+        let max_delay_for = |attempt: u32| {
+            (0..50)
+                .map(|_| backoff_with_jitter(attempt, BASE_BACKOFF_MS, MAX_BACKOFF_MS).as_millis() as u64)
+                .max()
+                .unwrap()
+        };
+
+        assert!(max_delay_for(8) >= max_delay_for(0));
+    }
+}
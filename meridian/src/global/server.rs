@@ -8,14 +8,16 @@
 //! - File watching for auto-reindexing
 
 use super::ipc::IpcServer;
-use super::registry::ProjectRegistryManager;
+use super::registry::{ProjectRegistry, ProjectRegistryManager};
 use super::storage::GlobalStorage;
 use super::sync::SyncManager;
 use super::watcher::{GlobalFileWatcher, WatcherConfig};
+use crate::chunking::CodeChunker;
+use crate::embeddings::EmbeddingEngine;
 use crate::rpc::{RpcServer, ServerStats, ToolRegistry, DatabasePool};
 use anyhow::{Context, Result};
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -221,14 +223,29 @@ impl GlobalServer {
                 Arc::clone(&watcher),
             ));
 
+            // Initialize the semantic chunker, re-chunked and re-embedded
+            // incrementally from the same file-change events as sync below.
+            // Normalized so `CodeChunker::query` can rank by the cheaper
+            // dot-product similarity instead of recomputing norms per chunk.
+            let chunker = Arc::new(CodeChunker::new(
+                Arc::clone(&self.storage),
+                Arc::new(Mutex::new(
+                    EmbeddingEngine::new_normalized()
+                        .context("Failed to create embedding engine for chunker")?,
+                )),
+            )?);
+
             // Set up change callback to sync manager
             let sync_manager_clone = Arc::clone(&sync_manager);
+            let chunker_clone = Arc::clone(&chunker);
+            let registry_for_chunker = Arc::clone(&self.registry_manager);
             watcher
                 .set_change_callback(Arc::new(move |event| {
                     debug!(
                         "File changed: {:?} (kind: {:?})",
                         event.path, event.kind
                     );
+                    let changed_path = event.path.clone();
 
                     // Handle the file change asynchronously
                     let sync_manager = Arc::clone(&sync_manager_clone);
@@ -237,6 +254,15 @@ impl GlobalServer {
                             warn!("Failed to handle file change: {}", e);
                         }
                     });
+
+                    // Re-chunk and re-embed the file for semantic code search
+                    let chunker = Arc::clone(&chunker_clone);
+                    let registry_manager = Arc::clone(&registry_for_chunker);
+                    tokio::spawn(async move {
+                        if let Err(e) = resync_chunks(&chunker, &registry_manager, &changed_path).await {
+                            warn!("Failed to re-chunk {:?}: {}", changed_path, e);
+                        }
+                    });
                 }))
                 .await;
 
@@ -475,6 +501,57 @@ impl GlobalServer {
 
 }
 
+/// Re-chunk and re-embed `path` for semantic code search in response to a
+/// file-change event, or clear its chunks if the file no longer exists (e.g.
+/// it was deleted). A no-op for paths outside any registered project.
+async fn resync_chunks(
+    chunker: &CodeChunker,
+    registry_manager: &ProjectRegistryManager,
+    path: &Path,
+) -> Result<()> {
+    let Some(project) = project_for_path(registry_manager, path).await? else {
+        return Ok(());
+    };
+    let relative_path = path.strip_prefix(&project.current_path).unwrap_or(path);
+
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => {
+            chunker
+                .handle_file_change(&project.identity.id, relative_path, &content)
+                .await?;
+        }
+        Err(_) => {
+            chunker.remove_file(&project.identity.id, relative_path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The most specific registered project containing `path`, i.e. the one with
+/// the longest matching root, so a file inside a nested monorepo package
+/// resolves to that package rather than the monorepo root.
+async fn project_for_path(
+    registry_manager: &ProjectRegistryManager,
+    path: &Path,
+) -> Result<Option<ProjectRegistry>> {
+    let mut best: Option<ProjectRegistry> = None;
+
+    for project in registry_manager.list_all().await? {
+        if !path.starts_with(&project.current_path) {
+            continue;
+        }
+        let is_more_specific = best
+            .as_ref()
+            .map_or(true, |b| project.current_path.components().count() > b.current_path.components().count());
+        if is_more_specific {
+            best = Some(project);
+        }
+    }
+
+    Ok(best)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
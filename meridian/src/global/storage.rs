@@ -269,6 +269,36 @@ impl GlobalStorage {
     pub async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
         Ok(self.db.get(key)?)
     }
+
+    /// Delete all keys under a raw prefix
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        let iter = self.db.prefix_iterator(prefix.as_bytes());
+        for item in iter {
+            let (key, _) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            self.db.delete(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize all JSON values stored under a raw prefix
+    pub async fn scan_raw<T: serde::de::DeserializeOwned>(&self, prefix: &str) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+        let iter = self.db.prefix_iterator(prefix.as_bytes());
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            values.push(
+                serde_json::from_slice(&value)
+                    .with_context(|| format!("Failed to deserialize value for key {:?}", key))?,
+            );
+        }
+        Ok(values)
+    }
 }
 
 #[cfg(test)]
@@ -390,4 +420,55 @@ mod tests {
         let updated = storage.get_project(&identity.full_id).await.unwrap().unwrap();
         assert_eq!(updated.path_history.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_put_and_get_raw() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = GlobalStorage::new(temp_dir.path()).await.unwrap();
+
+        storage.put_raw("raw:a", b"hello").await.unwrap();
+
+        assert_eq!(storage.get_raw("raw:a").await.unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(storage.get_raw("raw:missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix_only_removes_matching_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = GlobalStorage::new(temp_dir.path()).await.unwrap();
+
+        storage.put_raw("prefix:a:1", b"1").await.unwrap();
+        storage.put_raw("prefix:a:2", b"2").await.unwrap();
+        storage.put_raw("prefix:b:1", b"3").await.unwrap();
+
+        storage.delete_prefix("prefix:a:").await.unwrap();
+
+        assert_eq!(storage.get_raw("prefix:a:1").await.unwrap(), None);
+        assert_eq!(storage.get_raw("prefix:a:2").await.unwrap(), None);
+        assert_eq!(storage.get_raw("prefix:b:1").await.unwrap(), Some(b"3".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_scan_raw_deserializes_all_matching_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = GlobalStorage::new(temp_dir.path()).await.unwrap();
+
+        storage.put_raw("scan:1", &serde_json::to_vec(&1u32).unwrap()).await.unwrap();
+        storage.put_raw("scan:2", &serde_json::to_vec(&2u32).unwrap()).await.unwrap();
+        storage.put_raw("other:1", &serde_json::to_vec(&99u32).unwrap()).await.unwrap();
+
+        let mut values: Vec<u32> = storage.scan_raw("scan:").await.unwrap();
+        values.sort();
+
+        assert_eq!(values, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_scan_raw_empty_prefix_returns_empty_vec() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = GlobalStorage::new(temp_dir.path()).await.unwrap();
+
+        let values: Vec<u32> = storage.scan_raw("nothing-here:").await.unwrap();
+        assert!(values.is_empty());
+    }
 }
@@ -1,3 +1,4 @@
+pub mod chunking;
 pub mod codegen;
 pub mod config;
 pub mod context;